@@ -1,8 +1,8 @@
 use clap::Parser;
 use std::fmt;
 use moon_phase::MoonPhase;
-use std::time::SystemTime;
-use chrono::{Datelike,Timelike,DateTime,offset::Utc,TimeZone};
+use std::time::{Duration,SystemTime};
+use chrono::{Datelike,Timelike,DateTime,offset::Utc,TimeZone,SecondsFormat};
 use human_date_parser::from_human_time;
 
 // Unicode variation selectors (VS): these are invisible characters that will make the previous
@@ -58,6 +58,53 @@ const SOUTH_EMOJI_FACE: [&str; 8] = [
     "🌛",
 ];
 
+// Names accepted by --next, paired with the phase value (0 to 1) they target.  The intermediate
+// phases target the middle of the range that `phase_name` gives them.
+const PHASE_TARGETS: [(&str, f64); 8] = [
+    ("new", 0.0),
+    ("waxing-crescent", 0.125),
+    ("first-quarter", 0.25),
+    ("waxing-gibbous", 0.375),
+    ("full", 0.5),
+    ("waning-gibbous", 0.625),
+    ("last-quarter", 0.75),
+    ("waning-crescent", 0.875),
+];
+const ZODIAC_NAMES: [&str; 12] = [
+    "Pisces",
+    "Aries",
+    "Taurus",
+    "Gemini",
+    "Cancer",
+    "Leo",
+    "Virgo",
+    "Libra",
+    "Scorpio",
+    "Sagittarius",
+    "Capricorn",
+    "Aquarius",
+];
+// Ecliptic longitudes where each of the above signs ends, as in crate `moon-phase`.
+const ZODIAC_ANGLES: [f64; 12] = [
+    33.18, 51.16, 93.44, 119.48, 135.30, 173.34, 224.17, 242.57, 271.26,
+    302.49, 311.72, 348.58,
+];
+
+// Coarse step for the --next search.  Must be shorter than the time the moon spends in the
+// shortest zodiac sign (Capricorn, ~9.2° of longitude, about 14.7 hours at the moon's fastest
+// ~15°/day), so that no change is stepped over; 6 hours leaves over a factor of two to spare.
+const SEARCH_STEP: Duration = Duration::from_secs(6 * 60 * 60);
+// Bisect until the bracket is this narrow.
+const SEARCH_PRECISION: Duration = Duration::from_secs(60);
+// Give up after this long; every phase and sign comes around within one lunar month.
+const SEARCH_LIMIT: Duration = Duration::from_secs(40 * 24 * 60 * 60);
+
+// What --next is looking for.
+enum Target {
+    Phase(f64),
+    AnySign,
+    Sign(&'static str),
+}
 
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum Mode {
@@ -122,6 +169,19 @@ struct Cli {
     #[arg(short, long)]
     face_emoji: bool,
 
+    /// Instead of the current phase, show the date and time when the given phase next occurs:
+    /// new, waxing-crescent, first-quarter, waxing-gibbous, full, waning-gibbous,
+    /// last-quarter, or waning-crescent.  The four in-between phases (waxing-crescent etc.)
+    /// target the middle of their range, not the moment their name starts.
+    ///
+    /// With --zodiac, give a sign name (e.g. "leo") to find when the moon next enters it,
+    /// or "any" for the next change of sign.
+    ///
+    /// The search starts from the given date (or now) and is precise to within a minute
+    /// of what the algorithm predicts; the algorithm itself may be off by some hours.
+    /// With --numeric or --emoji, also show the phase or sign at that moment.
+    #[arg(long, value_name="PHASE")]
+    next: Option<String>,
 
     /// Date with optional time to query the moon phase
     /// (e.g. "2023-10-31", "2023-10-31 23:59:59", "Friday").
@@ -202,6 +262,90 @@ fn to_emoji(phase: f64,
         emoji_with_vs(emoji, vari)
 }
 
+fn str_to_target(name: &str, zodiac: bool) -> Result<Target, &'static str> {
+    // accept "First Quarter", "first_quarter" etc. as well as "first-quarter"
+    let name = name.trim().to_lowercase().replace([' ', '_'], "-");
+    if zodiac {
+        if name == "any" {
+            return Ok(Target::AnySign);
+        }
+        ZODIAC_NAMES.iter()
+            .find(|sign| sign.to_lowercase() == name)
+            .map(|sign| Target::Sign(sign))
+            .ok_or("Invalid zodiac sign")
+    } else {
+        PHASE_TARGETS.iter()
+            .find(|(phase_name, _)| *phase_name == name)
+            .map(|(_, phase)| Target::Phase(*phase))
+            .ok_or("Invalid phase")
+    }
+}
+
+// The crate's phase and longitude go negative before the year 2000; these normalise them to
+// 0 to 1 and 0 to 360° respectively.
+fn phase(moon: &MoonPhase) -> f64 {
+    moon.phase.rem_euclid(1.0)
+}
+
+fn longitude(moon: &MoonPhase) -> f64 {
+    moon.longitude.rem_euclid(360.0)
+}
+
+// The zodiac sign, like `moon.zodiac_name`, but also correct before the year 2000 (where the
+// crate's reads as Pisces at every moment).
+fn zodiac_sign(moon: &MoonPhase) -> &'static str {
+    let longitude = longitude(moon);
+    ZODIAC_ANGLES.iter()
+        .position(|angle| longitude < *angle)
+        .map_or(ZODIAC_NAMES[0], |i| ZODIAC_NAMES[i])
+}
+
+// Whether the target was reached somewhere after `before`, up to and including `after`.
+// Assumes the two moments are at most SEARCH_STEP apart.
+fn target_crossed(target: &Target, before: &MoonPhase, after: &MoonPhase) -> bool {
+    match target {
+        Target::Phase(p) => {
+            // How far past the target phase the moon is.  This grows steadily and wraps
+            // back to 0 exactly when the target is reached, which also takes care of the
+            // wraparound from ~0.99 to 0.0.
+            let past = |moon: &MoonPhase| (phase(moon) - p).rem_euclid(1.0);
+            past(after) < past(before)
+        },
+        // the sign is derived from the ecliptic longitude, which increases steadily
+        Target::AnySign => zodiac_sign(before) != zodiac_sign(after),
+        Target::Sign(sign) => zodiac_sign(before) != *sign && zodiac_sign(after) == *sign,
+    }
+}
+
+fn find_next(target: &Target, start: SystemTime) -> Option<SystemTime> {
+    // Step forward until the target falls between two steps.  The start itself is never
+    // a match, so if the target is happening right now we find the next occurrence.
+    let mut lo = start;
+    let mut lo_moon = MoonPhase::new(lo);
+    let mut hi = lo + SEARCH_STEP;
+    let mut hi_moon = MoonPhase::new(hi);
+    while !target_crossed(target, &lo_moon, &hi_moon) {
+        if hi.duration_since(start).ok()? > SEARCH_LIMIT {
+            return None;
+        }
+        (lo, lo_moon) = (hi, hi_moon);
+        hi = lo + SEARCH_STEP;
+        hi_moon = MoonPhase::new(hi);
+    }
+
+    // Then bisect the bracket.
+    while hi.duration_since(lo).ok()? > SEARCH_PRECISION {
+        let mid = lo + hi.duration_since(lo).ok()? / 2;
+        let mid_moon = MoonPhase::new(mid);
+        if target_crossed(target, &lo_moon, &mid_moon) {
+            hi = mid;
+        } else {
+            (lo, lo_moon) = (mid, mid_moon);
+        }
+    }
+    Some(hi)
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -227,7 +371,7 @@ fn main() {
         _ => EmojiVariation::Unspecified
     };
 
-    let moontime: SystemTime;
+    let mut moontime: SystemTime;
     if cli.date.is_some() {
         match str_to_system_time(cli.date.unwrap().as_str()) {
             Ok(t) => { moontime = t;} 
@@ -240,17 +384,44 @@ fn main() {
         moontime = SystemTime::now();
     }
 
+    if let Some(next) = cli.next.as_deref() {
+        let target = match str_to_target(next, cli.zodiac) {
+            Ok(t) => t,
+            Err(e) => {
+                println!("{}!", e);
+                std::process::exit(2);
+            }
+        };
+        match find_next(&target, moontime) {
+            Some(t) => { moontime = t; }
+            None => {
+                println!("Could not find the next occurrence!");
+                std::process::exit(2);
+            }
+        }
+        let datetime: DateTime<chrono::Local> = moontime.into();
+        let datetime = datetime.to_rfc3339_opts(SecondsFormat::Secs, false);
+        match mode {
+            // the date is the answer; other modes append the phase or sign at that moment
+            Mode::Name => {
+                println!("{}", datetime);
+                return;
+            },
+            _ => print!("{} ", datetime),
+        }
+    }
+
     let moon = MoonPhase::new(moontime);
 
     if cli.zodiac {
         match mode {
-            Mode::Name  => println!("{}", moon.zodiac_name),
+            Mode::Name  => println!("{}", zodiac_sign(&moon)),
             Mode::Numeric => {
-                println!("{:1.2}", moon.longitude);
+                println!("{:1.2}", longitude(&moon));
             },
             Mode::Emoji => {
                 let emoji = if cli.face_emoji {
-                    match moon.zodiac_name {
+                    match zodiac_sign(&moon) {
                         "Pisces"=> "🐟",
                         "Aries"=> "🐏",
                         "Taurus"=> "🐂",
@@ -266,7 +437,7 @@ fn main() {
                         _ => "🐍",
                     }
                 } else {
-                    match moon.zodiac_name {
+                    match zodiac_sign(&moon) {
                         "Pisces"=> "♓",
                         "Aries"=> "♈",
                         "Taurus"=> "♉",
@@ -287,10 +458,10 @@ fn main() {
         };
     } else {
         match mode {
-            Mode::Numeric => println!("{:1.2}", moon.phase),
+            Mode::Numeric => println!("{:1.2}", phase(&moon)),
             Mode::Name    => println!("{}", moon.phase_name),
             Mode::Emoji => {
-                let emoji = to_emoji(moon.phase,
+                let emoji = to_emoji(phase(&moon),
                                      cli.south_hemisphere,
                                      cli.face_emoji,
                                      emoji_variation);
@@ -300,3 +471,73 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32, s: u32) -> SystemTime {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, s).unwrap().into()
+    }
+
+    // Distance between two moments, in either order.
+    fn apart(a: SystemTime, b: SystemTime) -> Duration {
+        a.duration_since(b).or_else(|_| b.duration_since(a)).unwrap()
+    }
+
+    #[test]
+    fn new_moon_across_wraparound() {
+        let new_moon = find_next(&Target::Phase(0.0), utc(2023, 11, 1, 0, 0, 0)).unwrap();
+        // start shortly before, while the phase is still ~0.99
+        let start = new_moon - Duration::from_secs(3 * 60 * 60);
+        assert!(MoonPhase::new(start).phase > 0.99);
+        let found = find_next(&Target::Phase(0.0), start).unwrap();
+        assert!(found >= new_moon);
+        assert!(found.duration_since(new_moon).unwrap() <= SEARCH_PRECISION);
+    }
+
+    #[test]
+    fn phase_happening_now_finds_next_one() {
+        let full = find_next(&Target::Phase(0.5), utc(2023, 10, 14, 0, 0, 0)).unwrap();
+        let next_full = find_next(&Target::Phase(0.5), full).unwrap();
+        let days = next_full.duration_since(full).unwrap().as_secs_f64() / 86400.;
+        assert!((days - 29.53).abs() < 0.01, "{} days", days);
+    }
+
+    #[test]
+    fn next_sign() {
+        let found = find_next(&Target::Sign("Capricorn"), utc(2023, 10, 14, 0, 0, 0)).unwrap();
+        assert!(apart(found, utc(2023, 10, 22, 11, 6, 0)) <= SEARCH_PRECISION);
+    }
+
+    #[test]
+    fn next_sign_before_2000() {
+        let start = utc(1990, 1, 1, 0, 0, 0);
+        let any = find_next(&Target::AnySign, start).unwrap();
+        let leo = find_next(&Target::Sign("Leo"), start).unwrap();
+        assert!(any < leo);
+        let moon = MoonPhase::new(leo);
+        assert_eq!(zodiac_sign(&moon), "Leo");
+        // what --numeric shows: just past the start of Leo
+        let longitude = longitude(&moon);
+        assert!((135.30..136.0).contains(&longitude), "{}", longitude);
+    }
+
+    #[test]
+    fn next_phase_before_2000() {
+        let full = find_next(&Target::Phase(0.5), utc(1990, 1, 1, 0, 0, 0)).unwrap();
+        let moon = MoonPhase::new(full);
+        // what --numeric and --emoji show
+        assert!((phase(&moon) - 0.5).abs() < 0.001, "{}", phase(&moon));
+        let emoji = to_emoji(phase(&moon), false, false, EmojiVariation::Unspecified);
+        assert_eq!(emoji, "🌕");
+    }
+
+    #[test]
+    fn target_names() {
+        assert!(matches!(str_to_target("First Quarter", false), Ok(Target::Phase(p)) if p == 0.25));
+        assert!(matches!(str_to_target("first_quarter", false), Ok(Target::Phase(p)) if p == 0.25));
+        assert!(matches!(str_to_target("Leo", true), Ok(Target::Sign("Leo"))));
+        assert!(str_to_target("full", true).is_err());
+    }
+}